@@ -1,7 +1,14 @@
+#![allow(unexpected_cfgs)]
+#![allow(clippy::result_large_err)]
+
 use anchor_lang::prelude::*;
+use std::fmt;
+use std::str::FromStr;
 
 declare_id!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS");
 
+const HISTORY_LEN: usize = 16;
+
 
 #[program]
 mod enums {
@@ -11,31 +18,110 @@ mod enums {
         ctx: Context<Create>,
         name:String,
         loc:Location,
-        car:Car
-    ) -> ProgramResult {
+        car:Car,
+        bounds: (u32, u32),
+    ) -> Result<()> {
         let player = &mut ctx.accounts.player;
         player.authority = *ctx.accounts.authority.key;
         player.name = name;
         player.loc = loc;
         player.car = car;
+        player.bounds = bounds;
+        player.history = [Location::Up; HISTORY_LEN];
+        player.head = 0;
+        player.len = 0;
         Ok(())
     }
-    pub fn update_location(ctx: Context<Change>, loc:Location) -> ProgramResult {
+    pub fn update_location(ctx: Context<Change>, loc:Location) -> Result<()> {
         let player = &mut ctx.accounts.player;
+        if player.loc == loc {
+            return Err(ErrorCode::NoChange.into());
+        }
+        msg!("location: {} -> {}", player.loc, loc);
+        let prev = player.loc;
+        player.push_history(prev);
         player.loc = loc;
+        emit!(LocationChanged { authority: player.authority, old: prev, new: loc });
         Ok(())
     }
-    pub fn update_car(ctx: Context<Change>, car:Car) -> ProgramResult {
+    pub fn update_car(ctx: Context<Change>, car:Car) -> Result<()> {
         let player = &mut ctx.accounts.player;
-        player.car = car;
+        if player.car == car {
+            return Err(ErrorCode::NoChange.into());
+        }
+        msg!("car: {} -> {}", player.car, car);
+        let prev = player.car.clone();
+        player.car = car.clone();
+        emit!(CarChanged { authority: player.authority, old: prev, new: car });
+        Ok(())
+    }
+    pub fn update_location_str(ctx: Context<Change>, spec: String) -> Result<()> {
+        let player = &mut ctx.accounts.player;
+        let loc = Location::from_str(&spec)?;
+        if player.loc == loc {
+            return Err(ErrorCode::NoChange.into());
+        }
+        msg!("location: {} -> {}", player.loc, loc);
+        let prev = player.loc;
+        player.push_history(prev);
+        player.loc = loc;
+        emit!(LocationChanged { authority: player.authority, old: prev, new: loc });
+        Ok(())
+    }
+    pub fn clear_history(ctx: Context<Change>) -> Result<()> {
+        let player = &mut ctx.accounts.player;
+        player.history = [Location::Up; HISTORY_LEN];
+        player.head = 0;
+        player.len = 0;
+        Ok(())
+    }
+    pub fn update_car_str(ctx: Context<Change>, spec: String) -> Result<()> {
+        let player = &mut ctx.accounts.player;
+        let car = Car::from_str(&spec)?;
+        if player.car == car {
+            return Err(ErrorCode::NoChange.into());
+        }
+        msg!("car: {} -> {}", player.car, car);
+        let prev = player.car.clone();
+        player.car = car.clone();
+        emit!(CarChanged { authority: player.authority, old: prev, new: car });
+        Ok(())
+    }
+    pub fn move_player(ctx: Context<Change>, dir: Location, distance: u32) -> Result<()> {
+        let player = &mut ctx.accounts.player;
+        let (x, y) = match player.loc {
+            Location::Point { x, y } => (x, y),
+            _ => (0, 0),
+        };
+        let (new_x, new_y) = match dir {
+            Location::Up => (x, y.saturating_add(distance).min(player.bounds.1)),
+            Location::Down => (x, y.saturating_sub(distance)),
+            Location::Left => (x.saturating_sub(distance), y),
+            Location::Right => (x.saturating_add(distance).min(player.bounds.0), y),
+            Location::Point { x: px, y: py } => {
+                if px > player.bounds.0 || py > player.bounds.1 {
+                    return Err(ErrorCode::OutOfBounds.into());
+                }
+                (px, py)
+            }
+        };
+        let prev = player.loc;
+        let new_loc = Location::Point { x: new_x, y: new_y };
+        if new_loc == prev {
+            return Err(ErrorCode::NoChange.into());
+        }
+        player.push_history(prev);
+        player.loc = new_loc;
+        emit!(LocationChanged { authority: player.authority, old: prev, new: new_loc });
         Ok(())
     }
 }
 
 #[derive(Accounts)]
 pub struct Create<'info> {
-    #[account(init, payer = authority, space = 8 + 2000)]
+    #[account(init, payer = authority, space = 8 + 2000 + HISTORY_LEN * 9 + 2)]
     pub player: Account<'info, Player>,
+    #[account(mut)]
     pub authority: Signer<'info>,
     pub system_program: Program<'info, System>,
 }
@@ -49,21 +135,42 @@ pub struct Change<'info> {
 
 
 #[account]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Player {
     pub authority: Pubkey,
     pub name: String,
     pub loc: Location,
-    pub car: Car
+    pub car: Car,
+    pub bounds: (u32, u32),
+    pub history: [Location; HISTORY_LEN],
+    pub head: u8,
+    pub len: u8
+}
+
+impl Player {
+    pub fn push_history(&mut self, prev: Location) {
+        let idx = self.head as usize;
+        self.history[idx] = prev;
+        self.head = ((self.head as usize + 1) % HISTORY_LEN) as u8;
+        if (self.len as usize) < HISTORY_LEN {
+            self.len += 1;
+        }
+    }
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type"))]
 pub enum Car{
     Suv{ modal:String, price:u32, color:Color },
     Hatchback{ modal:String, price:u32, color:Color },
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type"))]
 pub enum Location{
+    #[default]
     Up,
     Down,
     Left,
@@ -71,8 +178,122 @@ pub enum Location{
     Point{x:u32, y:u32}
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type"))]
 pub enum Color{
     Red,
     Green
+}
+
+#[event]
+pub struct LocationChanged {
+    pub authority: Pubkey,
+    pub old: Location,
+    pub new: Location,
+}
+
+#[event]
+pub struct CarChanged {
+    pub authority: Pubkey,
+    pub old: Car,
+    pub new: Car,
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("The requested location is outside the player's bounds")]
+    OutOfBounds,
+    #[msg("Could not parse the provided spec")]
+    ParseFailure,
+    #[msg("The submitted value is the same as the current one")]
+    NoChange,
+}
+
+impl FromStr for Location {
+    type Err = ErrorCode;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let lower = s.to_lowercase();
+        match lower.as_str() {
+            "up" => return Ok(Location::Up),
+            "down" => return Ok(Location::Down),
+            "left" => return Ok(Location::Left),
+            "right" => return Ok(Location::Right),
+            _ => {}
+        }
+        let rest = lower.strip_prefix("point:").ok_or(ErrorCode::ParseFailure)?;
+        let (x_str, y_str) = rest.split_once(',').ok_or(ErrorCode::ParseFailure)?;
+        let x = x_str.parse::<u32>().map_err(|_| ErrorCode::ParseFailure)?;
+        let y = y_str.parse::<u32>().map_err(|_| ErrorCode::ParseFailure)?;
+        Ok(Location::Point { x, y })
+    }
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Location::Up => write!(f, "up"),
+            Location::Down => write!(f, "down"),
+            Location::Left => write!(f, "left"),
+            Location::Right => write!(f, "right"),
+            Location::Point { x, y } => write!(f, "point:{},{}", x, y),
+        }
+    }
+}
+
+impl FromStr for Color {
+    type Err = ErrorCode;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "red" => Ok(Color::Red),
+            "green" => Ok(Color::Green),
+            _ => Err(ErrorCode::ParseFailure),
+        }
+    }
+}
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Color::Red => write!(f, "red"),
+            Color::Green => write!(f, "green"),
+        }
+    }
+}
+
+impl FromStr for Car {
+    type Err = ErrorCode;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let mut parts = s.splitn(4, ':');
+        let kind = parts.next().ok_or(ErrorCode::ParseFailure)?;
+        let modal = parts.next().ok_or(ErrorCode::ParseFailure)?.to_string();
+        let price = parts
+            .next()
+            .ok_or(ErrorCode::ParseFailure)?
+            .parse::<u32>()
+            .map_err(|_| ErrorCode::ParseFailure)?;
+        let color = parts
+            .next()
+            .ok_or(ErrorCode::ParseFailure)?
+            .parse::<Color>()?;
+        match kind.to_lowercase().as_str() {
+            "suv" => Ok(Car::Suv { modal, price, color }),
+            "hatchback" => Ok(Car::Hatchback { modal, price, color }),
+            _ => Err(ErrorCode::ParseFailure),
+        }
+    }
+}
+
+impl fmt::Display for Car {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Car::Suv { modal, price, color } => write!(f, "suv:{}:{}:{}", modal, price, color),
+            Car::Hatchback { modal, price, color } => {
+                write!(f, "hatchback:{}:{}:{}", modal, price, color)
+            }
+        }
+    }
 }
\ No newline at end of file